@@ -1,3 +1,6 @@
+#![allow(clippy::needless_return)]
+#![allow(clippy::redundant_field_names)]
+
 use rustler::NifStruct;
 use unicode_segmentation::UnicodeSegmentation;
 
@@ -79,6 +82,22 @@ impl SLStringRange {
             utf16: SLRange::from_sta_len(range.utf16.start, len.utf16),
         };
     }
+    pub fn from_offsets(offsets: SLLength) -> Self {
+        return SLStringRange {
+            byte: SLRange::from_sta_len(offsets.byte, 0),
+            code: SLRange::from_sta_len(offsets.code, 0),
+            char: SLRange::from_sta_len(offsets.char, 0),
+            utf16: SLRange::from_sta_len(offsets.utf16, 0),
+        };
+    }
+    pub fn from_start_len(start: SLLength, len: SLLength) -> Self {
+        return SLStringRange {
+            byte: SLRange::from_sta_len(start.byte, len.byte),
+            code: SLRange::from_sta_len(start.code, len.code),
+            char: SLRange::from_sta_len(start.char, len.char),
+            utf16: SLRange::from_sta_len(start.utf16, len.utf16),
+        };
+    }
     pub fn shift_after(range: SLStringRange, prev: SLStringRange) -> Self {
         return SLStringRange {
             byte: SLRange::from_sta_len(prev.byte.stop + 1, range.byte.length),
@@ -114,6 +133,180 @@ impl SLLength {
             utf16: 0,
         };
     }
+    pub fn plus(self, other: SLLength) -> Self {
+        return SLLength {
+            byte: self.byte + other.byte,
+            code: self.code + other.code,
+            char: self.char + other.char,
+            utf16: self.utf16 + other.utf16,
+        };
+    }
+    pub fn minus(self, other: SLLength) -> Self {
+        return SLLength {
+            byte: self.byte - other.byte,
+            code: self.code - other.code,
+            char: self.char - other.char,
+            utf16: self.utf16 - other.utf16,
+        };
+    }
+}
+
+#[derive(Debug, rustler::NifUnitEnum, Clone, Copy, PartialEq, Eq)]
+pub enum SLUnit {
+    Byte,
+    Code,
+    Char,
+    Utf16,
+}
+
+impl SLLength {
+    pub fn unit(&self, unit: SLUnit) -> isize {
+        return match unit {
+            SLUnit::Byte => self.byte,
+            SLUnit::Code => self.code,
+            SLUnit::Char => self.char,
+            SLUnit::Utf16 => self.utf16,
+        };
+    }
+}
+
+fn grapheme_boundaries(string: &str) -> Vec<SLLength> {
+    let mut boundaries: Vec<SLLength> = vec![SLLength::zero()];
+    let mut running = SLLength::zero();
+    for grapheme in string.graphemes(true) {
+        running = running.plus(SLLength {
+            byte: isize::try_from(grapheme.len()).unwrap(),
+            code: isize::try_from(grapheme.chars().count()).unwrap(),
+            char: 1,
+            utf16: isize::try_from(grapheme.encode_utf16().count()).unwrap(),
+        });
+        boundaries.push(running);
+    }
+    return boundaries;
+}
+
+fn offsets_at(string: &str, offset: isize, from: SLUnit) -> SLLength {
+    let boundaries = grapheme_boundaries(string);
+    for window in boundaries.windows(2) {
+        if window[1].unit(from) > offset {
+            return window[0];
+        }
+    }
+    return *boundaries.last().unwrap();
+}
+
+#[rustler::nif]
+fn convert_offset(string: &str, offset: isize, from: SLUnit, to: SLUnit) -> isize {
+    return offsets_at(string, offset, from).unit(to);
+}
+
+#[rustler::nif]
+fn convert_point(string: &str, offset: isize, from: SLUnit) -> SLStringRange {
+    return SLStringRange::from_offsets(offsets_at(string, offset, from));
+}
+
+#[rustler::nif]
+fn boundary_table(string: &str) -> Vec<(isize, isize, isize, isize)> {
+    return grapheme_boundaries(string)
+        .into_iter()
+        .map(|b| (b.byte, b.code, b.char, b.utf16))
+        .collect();
+}
+
+fn floor_index(boundaries: &[SLLength], unit: SLUnit, value: isize) -> usize {
+    return boundaries.partition_point(|b| b.unit(unit) <= value).saturating_sub(1);
+}
+
+fn ceil_index(boundaries: &[SLLength], unit: SLUnit, value: isize) -> usize {
+    let last = boundaries.len() - 1;
+    return boundaries.partition_point(|b| b.unit(unit) < value).min(last);
+}
+
+fn slice_core(string: &str, range: SLStringRange) -> (String, SLStringRange) {
+    let boundaries = grapheme_boundaries(string);
+
+    // byte/code/char/utf16 can each claim a different cluster boundary for
+    // the same requested span; snap outward so the slice never cuts a
+    // cluster in any of the four coordinate systems.
+    let start_idx = [
+        floor_index(&boundaries, SLUnit::Byte, range.byte.start),
+        floor_index(&boundaries, SLUnit::Code, range.code.start),
+        floor_index(&boundaries, SLUnit::Char, range.char.start),
+        floor_index(&boundaries, SLUnit::Utf16, range.utf16.start),
+    ]
+    .into_iter()
+    .min()
+    .unwrap();
+
+    let stop_idx = [
+        ceil_index(&boundaries, SLUnit::Byte, range.byte.stop + 1),
+        ceil_index(&boundaries, SLUnit::Code, range.code.stop + 1),
+        ceil_index(&boundaries, SLUnit::Char, range.char.stop + 1),
+        ceil_index(&boundaries, SLUnit::Utf16, range.utf16.stop + 1),
+    ]
+    .into_iter()
+    .max()
+    .unwrap()
+    .max(start_idx);
+
+    let start = boundaries[start_idx];
+    let stop = boundaries[stop_idx];
+    let text = &string[start.byte as usize..stop.byte as usize];
+    let adjusted = SLStringRange::from_start_len(start, stop.minus(start));
+    return (text.to_string(), adjusted);
+}
+
+#[rustler::nif]
+fn slice(string: &str, range: SLStringRange) -> (String, SLStringRange) {
+    return slice_core(string, range);
+}
+
+fn grapheme_count_at_byte(boundaries: &[SLLength], byte_offset: usize) -> isize {
+    let byte_offset = isize::try_from(byte_offset).unwrap();
+    let idx = boundaries.partition_point(|b| b.byte <= byte_offset).saturating_sub(1);
+    return boundaries[idx].char;
+}
+
+fn find_all_core(haystack: &str, needle: &str, require_grapheme_boundary: Option<bool>) -> Vec<SLStringRange> {
+    let require_boundary = require_grapheme_boundary.unwrap_or(false);
+    let boundaries = grapheme_boundaries(haystack);
+    let boundary_bytes: std::collections::HashSet<isize> = if require_boundary {
+        boundaries.iter().map(|b| b.byte).collect()
+    } else {
+        std::collections::HashSet::new()
+    };
+
+    let mut result: Vec<SLStringRange> = vec![];
+    let mut running = SLLength::zero();
+    let mut prev_byte = 0usize;
+    for (byte_start, matched) in haystack.match_indices(needle) {
+        let byte_stop = byte_start + matched.len();
+        if require_boundary
+            && (!boundary_bytes.contains(&isize::try_from(byte_start).unwrap())
+                || !boundary_bytes.contains(&isize::try_from(byte_stop).unwrap()))
+        {
+            continue;
+        }
+        // byte/code/utf16 stay additive across the slice since match_indices
+        // only splits on codepoint boundaries, but a grapheme cluster can
+        // straddle that split, so `char` is looked up from the boundary
+        // table instead of summed per-slice.
+        running = running.plus(SLLength::new(&haystack[prev_byte..byte_start]));
+        let match_len = SLLength::new(matched);
+        let mut start = running;
+        start.char = grapheme_count_at_byte(&boundaries, byte_start);
+        let mut len = match_len;
+        len.char = grapheme_count_at_byte(&boundaries, byte_stop) - start.char;
+        result.push(SLStringRange::from_start_len(start, len));
+        running = running.plus(match_len);
+        prev_byte = byte_stop;
+    }
+    return result;
+}
+
+#[rustler::nif]
+fn find_all(haystack: &str, needle: &str, require_grapheme_boundary: Option<bool>) -> Vec<SLStringRange> {
+    return find_all_core(haystack, needle, require_grapheme_boundary);
 }
 
 #[rustler::nif]
@@ -142,6 +335,33 @@ fn ranges(strings: Vec<&str>, r: SLStringRange) -> Vec<SLStringRange> {
 fn length(string: &str) -> SLLength {
     return SLLength::new(string);
 }
+
+fn length_utf16_core(units: Vec<u16>) -> SLLength {
+    let mut decoded = String::new();
+    let mut utf16 = 0isize;
+    for result in char::decode_utf16(units) {
+        match result {
+            Ok(c) => {
+                utf16 += isize::try_from(c.len_utf16()).unwrap();
+                decoded.push(c);
+            }
+            Err(_) => {
+                utf16 += 1;
+            }
+        }
+    }
+    return SLLength {
+        byte: isize::try_from(decoded.len()).unwrap(),
+        code: isize::try_from(decoded.chars().count()).unwrap(),
+        char: isize::try_from(decoded.graphemes(true).count()).unwrap(),
+        utf16: utf16,
+    };
+}
+
+#[rustler::nif]
+fn length_utf16(units: Vec<u16>) -> SLLength {
+    return length_utf16_core(units);
+}
 #[rustler::nif]
 fn replace(range: SLStringRange, string: &str) -> SLStringRange {
     return SLStringRange::replace(range, SLLength::new(string));
@@ -159,7 +379,147 @@ rustler::init!(
         range_from_range,
         range_from_point,
         length,
+        length_utf16,
         replace,
-        shift_after
+        shift_after,
+        convert_offset,
+        convert_point,
+        find_all,
+        slice,
+        boundary_table
     ]
 );
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn convert_offset_clamps_mid_grapheme_to_cluster_start() {
+        // "e" + combining acute accent (U+0301) is one grapheme, two codepoints.
+        let s = "e\u{0301}";
+        assert_eq!(offsets_at(s, 1, SLUnit::Code).unit(SLUnit::Code), 0);
+        assert_eq!(offsets_at(s, 1, SLUnit::Code).unit(SLUnit::Byte), 0);
+    }
+
+    #[test]
+    fn convert_offset_clamps_mid_surrogate_pair_to_cluster_start() {
+        // U+1D11E is astral, encoded as a UTF-16 surrogate pair.
+        let s = "\u{1D11E}";
+        assert_eq!(offsets_at(s, 1, SLUnit::Utf16).unit(SLUnit::Utf16), 0);
+    }
+
+    #[test]
+    fn convert_offset_past_end_returns_total_length() {
+        let s = "ab";
+        assert_eq!(offsets_at(s, 100, SLUnit::Code).unit(SLUnit::Byte), 2);
+    }
+
+    #[test]
+    fn convert_point_clamps_to_cluster_start() {
+        let s = "e\u{0301}";
+        let point = SLStringRange::from_offsets(offsets_at(s, 1, SLUnit::Code));
+        assert_eq!(point.byte.start, 0);
+        assert_eq!(point.char.start, 0);
+    }
+
+    #[test]
+    fn find_all_reports_zero_grapheme_length_for_sub_cluster_match() {
+        // "e" + combining acute accent is one grapheme; matching just "e"
+        // splits the cluster and must not be reported as a whole grapheme.
+        let haystack = "e\u{0301}";
+        let matches = find_all_core(haystack, "e", None);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].byte.start, 0);
+        assert_eq!(matches[0].byte.length, 1);
+        assert_eq!(matches[0].char.length, 0);
+    }
+
+    #[test]
+    fn find_all_skips_sub_cluster_matches_when_boundary_required() {
+        let haystack = "e\u{0301}";
+        let matches = find_all_core(haystack, "e", Some(true));
+        assert!(matches.is_empty());
+    }
+
+    #[test]
+    fn find_all_matches_whole_cluster_when_boundary_required() {
+        let haystack = "e\u{0301}";
+        let matches = find_all_core(haystack, "e\u{0301}", Some(true));
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].char.start, 0);
+        assert_eq!(matches[0].char.length, 1);
+    }
+
+    #[test]
+    fn slice_returns_whole_cluster_for_aligned_range() {
+        let string = "e\u{0301}";
+        let range = SLStringRange::from_start_len(SLLength::zero(), SLLength::new(string));
+        let (text, adjusted) = slice_core(string, range);
+        assert_eq!(text, string);
+        assert_eq!(adjusted.char.length, 1);
+        assert_eq!(adjusted.byte.length, 3);
+    }
+
+    #[test]
+    fn slice_snaps_outward_when_byte_range_disagrees_with_char_range() {
+        let string = "e\u{0301}";
+        // char says "nothing" but byte spans the whole two-codepoint cluster;
+        // the wider coordinate system must win so the cluster isn't cut.
+        let range = SLStringRange {
+            byte: SLRange::from_sta_sto(0, 2),
+            code: SLRange::from_sta_len(0, 0),
+            char: SLRange::from_sta_len(0, 0),
+            utf16: SLRange::from_sta_len(0, 0),
+        };
+        let (text, adjusted) = slice_core(string, range);
+        assert_eq!(text, string);
+        assert_eq!(adjusted.char.length, 1);
+    }
+
+    #[test]
+    fn length_utf16_counts_lone_surrogate_as_one_utf16_unit_only() {
+        let len = length_utf16_core(vec![0xD800]);
+        assert_eq!(len.utf16, 1);
+        assert_eq!(len.code, 0);
+        assert_eq!(len.byte, 0);
+        assert_eq!(len.char, 0);
+    }
+
+    #[test]
+    fn length_utf16_counts_astral_pair_as_two_utf16_units_one_scalar() {
+        // U+1F600 GRINNING FACE as its UTF-16 surrogate pair.
+        let len = length_utf16_core(vec![0xD83D, 0xDE00]);
+        assert_eq!(len.utf16, 2);
+        assert_eq!(len.code, 1);
+        assert_eq!(len.char, 1);
+        assert_eq!(len.byte, "\u{1F600}".len() as isize);
+    }
+
+    #[test]
+    fn length_utf16_skips_lone_surrogates_in_code_and_byte_but_not_utf16() {
+        let len = length_utf16_core(vec![0x0041, 0xD800, 0x0042]);
+        assert_eq!(len.utf16, 3);
+        assert_eq!(len.code, 2);
+        assert_eq!(len.char, 2);
+        assert_eq!(len.byte, 2);
+    }
+
+    #[test]
+    fn boundary_table_has_one_entry_per_boundary_plus_terminal() {
+        let table: Vec<(isize, isize, isize, isize)> = grapheme_boundaries("ab")
+            .into_iter()
+            .map(|b| (b.byte, b.code, b.char, b.utf16))
+            .collect();
+        assert_eq!(table, vec![(0, 0, 0, 0), (1, 1, 1, 1), (2, 2, 2, 2)]);
+    }
+
+    #[test]
+    fn boundary_table_places_a_single_entry_per_grapheme_cluster() {
+        let table: Vec<(isize, isize, isize, isize)> = grapheme_boundaries("e\u{0301}")
+            .into_iter()
+            .map(|b| (b.byte, b.code, b.char, b.utf16))
+            .collect();
+        assert_eq!(table, vec![(0, 0, 0, 0), (3, 2, 1, 2)]);
+    }
+}